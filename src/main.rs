@@ -0,0 +1,8 @@
+mod ragenix;
+
+fn main() {
+    if let Err(err) = ragenix::run() {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
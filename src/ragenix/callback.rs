@@ -0,0 +1,29 @@
+use age::secrecy::SecretString;
+
+/// Minimal, non-interactive [`age::Callbacks`] implementation used when
+/// talking to `age-plugin-*` binaries.
+///
+/// ragenix runs unattended (in `--edit`/`--rekey`/CI contexts), so it has no
+/// way to prompt for passphrases or free-form confirmations; it only
+/// surfaces the plugin's progress messages on stderr so the user knows e.g.
+/// to touch a hardware key.
+#[derive(Debug, Clone, Copy)]
+pub struct UiCallbacks;
+
+impl age::Callbacks for UiCallbacks {
+    fn display_message(&self, message: &str) {
+        eprintln!("{message}");
+    }
+
+    fn confirm(&self, _message: &str, _yes_string: &str, _no_string: Option<&str>) -> Option<bool> {
+        None
+    }
+
+    fn request_public_string(&self, _description: &str) -> Option<String> {
+        None
+    }
+
+    fn request_passphrase(&self, _description: &str) -> Option<SecretString> {
+        None
+    }
+}
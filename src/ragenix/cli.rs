@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Parser};
+
+/// A reimplementation of agenix in Rust.
+#[derive(Debug, Parser)]
+#[command(name = "ragenix", version, about)]
+#[command(group(ArgGroup::new("action").required(true).args(["edit", "rekey", "schema", "verify"])))]
+pub struct Cli {
+    /// Edits the given secret file(s), decrypting each (if it exists) before
+    /// opening $EDITOR, and re-encrypting it on save.
+    #[arg(short, long, value_name = "FILE", num_args = 1..)]
+    pub edit: Vec<PathBuf>,
+
+    /// Re-encrypts all secrets declared in the rules file with their
+    /// currently configured recipients.
+    #[arg(short, long)]
+    pub rekey: bool,
+
+    /// Restricts `--rekey` to secrets whose path matches this glob. May be
+    /// given more than once; every pattern must match at least one secret.
+    #[arg(long, requires = "rekey", value_name = "GLOB")]
+    pub only: Vec<String>,
+
+    /// Prints the JSON schema for the rules file and exits.
+    #[arg(long)]
+    pub schema: bool,
+
+    /// Confirms every secret is encrypted to exactly its declared
+    /// recipients and matches the `secrets.lock` manifest, without
+    /// modifying anything. Exits non-zero on any mismatch. Requires a
+    /// `secrets.lock` from a previous --rekey to compare against.
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Identity to use when decrypting: either a path to a private key file,
+    /// or a `scheme:value` reference to an external secret store (`kms:` or
+    /// `ssm:`). Defaults to ~/.ssh/id_rsa and ~/.ssh/id_ed25519.
+    #[arg(short, long, value_name = "IDENTITY", num_args = 1..)]
+    pub identity: Vec<String>,
+
+    /// Path to the rules file describing the secrets and their recipients.
+    #[arg(long, default_value = "./secrets.nix", value_name = "RULES_FILE")]
+    pub rules: PathBuf,
+
+    /// Number of secrets to rekey concurrently. Defaults to the number of
+    /// available CPUs.
+    #[arg(short, long, default_value_t = default_jobs(), value_name = "N")]
+    pub jobs: usize,
+
+    /// Enable verbose logging.
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+/// The default `--jobs` value: one worker per available CPU.
+fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
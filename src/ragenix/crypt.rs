@@ -0,0 +1,50 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Write},
+};
+
+use age::{
+    armor::{ArmoredReader, ArmoredWriter, Format},
+    Decryptor, Encryptor,
+};
+
+use super::error::{RagenixError, Result};
+
+/// Decrypts the age-armored file at `path` using the first identity in
+/// `identities` that matches one of its stanzas.
+pub fn decrypt(
+    path: &std::path::Path,
+    identities: &[Box<dyn age::Identity + Send + Sync>],
+) -> Result<Vec<u8>> {
+    let file = File::open(path)?;
+    let armored = ArmoredReader::new(BufReader::new(file));
+
+    let decryptor = match Decryptor::new_buffered(armored)? {
+        Decryptor::Recipients(d) => d,
+        Decryptor::Passphrase(_) => {
+            return Err(RagenixError::Decrypt(age::DecryptError::InvalidHeader));
+        }
+    };
+
+    let mut plaintext = Vec::new();
+    let identities = identities
+        .iter()
+        .map(|i| i.as_ref() as &dyn age::Identity);
+    decryptor.decrypt(identities)?.read_to_end(&mut plaintext)?;
+
+    Ok(plaintext)
+}
+
+/// Encrypts `plaintext` to `recipients`, returning an ASCII-armored age file.
+pub fn encrypt(recipients: Vec<Box<dyn age::Recipient + Send>>, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let encryptor = Encryptor::with_recipients(recipients)
+        .expect("at least one recipient, since the rules schema requires publicKeys to be non-empty");
+
+    let mut encrypted = Vec::new();
+    let armor = ArmoredWriter::wrap_output(&mut encrypted, Format::AsciiArmor)?;
+    let mut writer = encryptor.wrap_output(armor)?;
+    writer.write_all(plaintext)?;
+    writer.finish()?.finish()?;
+
+    Ok(encrypted)
+}
@@ -0,0 +1,100 @@
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use thiserror::Error;
+
+use super::{crypt, error::Result, identity, recipient, rules::Rules};
+
+#[derive(Debug, Error)]
+pub enum EditError {
+    #[error("'{}' is not declared in '{}'", path.display(), rules.display())]
+    NotDeclared { path: PathBuf, rules: PathBuf },
+}
+
+/// Edits every file in `files` in turn. See [`edit_one`].
+///
+/// Identities are resolved at most once and shared across every file, rather
+/// than per file: a `kms:`/`ssm:` identity spec involves a network round-trip
+/// to its secret store, and re-running that for each of several `--edit`
+/// files would be wasteful and slow.
+pub fn edit(files: &[PathBuf], rules: &Rules, identity_specs: &[String]) -> Result<()> {
+    let mut identities = None;
+    for file in files {
+        edit_one(file, rules, identity_specs, &mut identities)?;
+    }
+    Ok(())
+}
+
+/// Decrypts `file` (if it already exists), opens it in `$EDITOR`, and
+/// re-encrypts it to the recipients declared for it in `rules`.
+///
+/// If the file is unchanged after editing, re-encryption (and the resulting
+/// new ciphertext/ivs) is skipped entirely, so the file on disk is left
+/// byte-for-byte as it was.
+fn edit_one(
+    file: &Path,
+    rules: &Rules,
+    identity_specs: &[String],
+    identities: &mut Option<Vec<Box<dyn age::Identity + Send + Sync>>>,
+) -> Result<()> {
+    let key = rules
+        .find_key(&file.to_string_lossy())?
+        .ok_or_else(|| EditError::NotDeclared {
+            path: file.to_path_buf(),
+            rules: rules.path.clone(),
+        })?
+        .clone();
+    let secret = &rules.secrets[&key];
+
+    let full_path = rules.secret_path(&key)?;
+
+    let plaintext = if full_path.exists() {
+        let identities = match identities {
+            Some(identities) => identities,
+            None => identities.insert(identity::load(identity_specs)?),
+        };
+        crypt::decrypt(&full_path, identities)?
+    } else {
+        Vec::new()
+    };
+
+    let tmp_dir = tempfile::Builder::new().prefix("ragenix").tempdir()?;
+    fs::set_permissions(tmp_dir.path(), fs::Permissions::from_mode(0o700))?;
+
+    let tmp_path = tmp_dir.path().join(file.file_name().unwrap_or_default());
+    fs::write(&tmp_path, &plaintext)?;
+    fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))?;
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_owned());
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh") // $0, unused
+        .arg(&tmp_path)
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("{editor} exited with {status}")).into());
+    }
+
+    let edited = fs::read(&tmp_path)?;
+
+    if full_path.exists() && edited == plaintext {
+        let display_path = fs::canonicalize(&full_path).unwrap_or(full_path);
+        println!("{} wasn't changed, skipping re-encryption.", display_path.display());
+        return Ok(());
+    }
+
+    let recipients = recipient::parse(&secret.public_keys)?;
+    let ciphertext = crypt::encrypt(recipients, &edited)?;
+
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&full_path, ciphertext)?;
+
+    Ok(())
+}
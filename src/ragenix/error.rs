@@ -0,0 +1,42 @@
+use thiserror::Error;
+
+use super::{
+    edit::EditError, identity::IdentityError, recipient::RecipientError, rekey::RekeyError,
+    rules::RulesError, verify::VerifyError,
+};
+
+/// The top-level error type for ragenix.
+///
+/// Every fallible operation in the crate eventually bubbles up into one of
+/// these variants, which [`main`](crate::main) renders with an `error:` prefix.
+#[derive(Debug, Error)]
+pub enum RagenixError {
+    #[error(transparent)]
+    Rules(#[from] RulesError),
+
+    #[error(transparent)]
+    Edit(#[from] EditError),
+
+    #[error(transparent)]
+    Recipient(#[from] RecipientError),
+
+    #[error(transparent)]
+    Identity(#[from] IdentityError),
+
+    #[error(transparent)]
+    Verify(#[from] VerifyError),
+
+    #[error(transparent)]
+    Rekey(#[from] RekeyError),
+
+    #[error("{0}")]
+    Decrypt(#[from] age::DecryptError),
+
+    #[error("{0}")]
+    Encrypt(#[from] age::EncryptError),
+
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, RagenixError>;
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use age::plugin;
+use base64::Engine;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+use super::callback::UiCallbacks;
+
+#[derive(Debug, Error)]
+pub enum IdentityError {
+    #[error(
+        "No usable identity or identities found. Pass one or more identity files with \
+         --identity, or make sure ~/.ssh/id_rsa or ~/.ssh/id_ed25519 exist."
+    )]
+    NoUsableIdentities,
+
+    #[error("Failed to read identity file '{}': {source}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Failed to resolve age plugin for identities in '{}': {source}", path.display())]
+    PluginResolve {
+        path: PathBuf,
+        #[source]
+        source: age::DecryptError,
+    },
+
+    #[error("'{spec}' did not resolve to any usable age identity")]
+    ProviderNoIdentities { spec: String },
+
+    #[error("Failed to resolve identity '{spec}' from {provider}: {message}")]
+    ProviderFailed {
+        spec: String,
+        provider: &'static str,
+        message: String,
+    },
+}
+
+/// Loads the identities used for decryption.
+///
+/// Each entry in `specs` is either a plain path to a local identity file, or a
+/// `scheme:value` reference to an external secret store, dispatched by
+/// [`provider_for`]. If `specs` is empty, falls back to `~/.ssh/id_rsa` and
+/// `~/.ssh/id_ed25519`, silently skipping whichever of those does not exist.
+/// If none of the default paths exist either, this is a hard error: with no
+/// identity source at all, there is nothing to even attempt decryption with.
+///
+/// Explicitly-passed `--identity` entries are not filtered by existence here:
+/// a file that cannot be read is an error at this point, while a file that
+/// holds no identities at all resolves to none, so decryption then fails with
+/// "No matching keys found".
+pub fn load(specs: &[String]) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError> {
+    let specs: Vec<String> = if specs.is_empty() {
+        default_paths()
+            .into_iter()
+            .filter(|p| p.exists())
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect()
+    } else {
+        specs.to_vec()
+    };
+
+    if specs.is_empty() {
+        return Err(IdentityError::NoUsableIdentities);
+    }
+
+    let mut identities = Vec::new();
+    for spec in &specs {
+        identities.extend(provider_for(spec).resolve()?);
+    }
+    Ok(identities)
+}
+
+fn default_paths() -> Vec<PathBuf> {
+    let Some(home) = std::env::var_os("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+    vec![home.join(".ssh/id_rsa"), home.join(".ssh/id_ed25519")]
+}
+
+/// A source that can be resolved to one or more age identities at runtime.
+///
+/// This is what makes `--identity` usable in environments that can't place a
+/// long-lived private key on the local filesystem: the master identity lives
+/// in an external secret store, and is only ever pulled into memory for the
+/// lifetime of the current command, zeroized afterwards, and never written to
+/// the temporary directory [`super::edit`] uses while a secret is being
+/// edited.
+trait IdentityProvider {
+    fn resolve(&self) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError>;
+}
+
+/// Resolves a single `--identity` entry to the [`IdentityProvider`] that
+/// should handle it, dispatching on its `scheme:` prefix. Entries with no
+/// recognized scheme are treated as a plain path, which is the default (and
+/// only, prior to this) provider.
+fn provider_for(spec: &str) -> Box<dyn IdentityProvider> {
+    if let Some(rest) = spec.strip_prefix("kms:") {
+        Box::new(KmsProvider(rest.to_owned()))
+    } else if let Some(rest) = spec.strip_prefix("ssm:") {
+        Box::new(SsmProvider(rest.to_owned()))
+    } else {
+        Box::new(PathProvider(PathBuf::from(spec)))
+    }
+}
+
+/// The default provider: reads a local identity file, exactly as `--identity`
+/// behaved before external providers existed.
+struct PathProvider(PathBuf);
+
+impl IdentityProvider for PathProvider {
+    fn resolve(&self) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError> {
+        load_file(&self.0)
+    }
+}
+
+/// `kms:<path>`: decrypts a KMS-wrapped age identity. `<path>` is a local file
+/// holding the raw binary ciphertext blob produced by `aws kms encrypt` (it is
+/// passed as `fileb://<path>`, so the file must not be base64-encoded);
+/// KMS recovers the key to use from the blob's own metadata, so no key ID
+/// needs to be passed here.
+struct KmsProvider(String);
+
+impl IdentityProvider for KmsProvider {
+    fn resolve(&self) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError> {
+        let spec = format!("kms:{}", self.0);
+
+        let plaintext = Zeroizing::new(run_aws(
+            &spec,
+            "aws kms decrypt",
+            &[
+                "kms",
+                "decrypt",
+                "--ciphertext-blob",
+                &format!("fileb://{}", self.0),
+                "--output",
+                "text",
+                "--query",
+                "Plaintext",
+            ],
+        )?);
+
+        let decoded = Zeroizing::new(
+            base64::engine::general_purpose::STANDARD
+                .decode(plaintext.trim())
+                .map_err(|source| IdentityError::ProviderFailed {
+                    spec: spec.clone(),
+                    provider: "kms",
+                    message: source.to_string(),
+                })?,
+        );
+        let text = Zeroizing::new(String::from_utf8(decoded.to_vec()).map_err(|source| {
+            IdentityError::ProviderFailed {
+                spec: spec.clone(),
+                provider: "kms",
+                message: source.to_string(),
+            }
+        })?);
+
+        parse_identity_text(&text, &spec)
+    }
+}
+
+/// `ssm:<name>`: pulls an age identity directly out of a `SecureString`
+/// parameter in AWS SSM Parameter Store.
+struct SsmProvider(String);
+
+impl IdentityProvider for SsmProvider {
+    fn resolve(&self) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError> {
+        let spec = format!("ssm:{}", self.0);
+        let text = Zeroizing::new(run_aws(
+            &spec,
+            "aws ssm get-parameter",
+            &[
+                "ssm",
+                "get-parameter",
+                "--name",
+                &self.0,
+                "--with-decryption",
+                "--query",
+                "Parameter.Value",
+                "--output",
+                "text",
+            ],
+        )?);
+
+        parse_identity_text(&text, &spec)
+    }
+}
+
+/// Shells out to the `aws` CLI and returns its stdout, trimmed. `name` is the
+/// human-readable form of the command, used in error messages.
+fn run_aws(spec: &str, name: &'static str, args: &[&str]) -> Result<String, IdentityError> {
+    let output = Command::new("aws")
+        .args(args)
+        .output()
+        .map_err(|source| IdentityError::ProviderFailed {
+            spec: spec.to_owned(),
+            provider: name,
+            message: source.to_string(),
+        })?;
+
+    if !output.status.success() {
+        return Err(IdentityError::ProviderFailed {
+            spec: spec.to_owned(),
+            provider: name,
+            message: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// The result of classifying each line of an identity source as a native age
+/// identity, an age plugin identity stanza (batched per plugin name, mirroring
+/// [`super::recipient::parse`]), or neither.
+#[derive(Default)]
+struct Classified {
+    native: Vec<age::x25519::Identity>,
+    plugins: HashMap<String, Vec<plugin::Identity>>,
+    unrecognized: bool,
+}
+
+fn classify(text: &str) -> Classified {
+    let mut classified = Classified::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Ok(identity) = line.parse::<age::x25519::Identity>() {
+            classified.native.push(identity);
+        } else if let Ok(identity) = line.parse::<plugin::Identity>() {
+            classified
+                .plugins
+                .entry(identity.plugin().to_owned())
+                .or_default()
+                .push(identity);
+        } else {
+            classified.unrecognized = true;
+        }
+    }
+
+    classified
+}
+
+/// Boxes up a [`Classified`] result, resolving one `age-plugin-<name>` plugin
+/// per distinct plugin name along the way. `path` is used only for error messages,
+/// and may be a real file path or a `scheme:value` identity spec.
+fn into_identities(
+    classified: Classified,
+    path: &Path,
+) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError> {
+    let mut identities: Vec<Box<dyn age::Identity + Send + Sync>> = classified
+        .native
+        .into_iter()
+        .map(|i| Box::new(i) as Box<dyn age::Identity + Send + Sync>)
+        .collect();
+
+    for (name, stanzas) in classified.plugins {
+        let plugin = plugin::IdentityPluginV1::new(&name, &stanzas, UiCallbacks).map_err(|source| {
+            IdentityError::PluginResolve {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?;
+        identities.push(Box::new(plugin));
+    }
+
+    Ok(identities)
+}
+
+/// Parses identity material resolved from an external secret store: one or
+/// more native age identities (`AGE-SECRET-KEY-1...`) and/or age plugin
+/// identity stanzas (`AGE-PLUGIN-...`). Unlike a local identity file, this
+/// never falls back to parsing an SSH key, since cloud secret stores hand
+/// back a single identity value rather than a file we could otherwise
+/// interpret as one.
+fn parse_identity_text(
+    text: &str,
+    spec: &str,
+) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError> {
+    let classified = classify(text);
+
+    if classified.native.is_empty() && classified.plugins.is_empty() {
+        return Err(IdentityError::ProviderNoIdentities {
+            spec: spec.to_owned(),
+        });
+    }
+
+    into_identities(classified, Path::new(spec))
+}
+
+/// Parses a single identity file, which may contain native age identities
+/// (one or more `AGE-SECRET-KEY-1...` lines), age plugin identity stanzas
+/// (`AGE-PLUGIN-...`), or a single SSH private key.
+fn load_file(path: &Path) -> Result<Vec<Box<dyn age::Identity + Send + Sync>>, IdentityError> {
+    let contents = Zeroizing::new(fs::read_to_string(path).map_err(|source| IdentityError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?);
+
+    let classified = classify(&contents);
+
+    // The file contained no lines we recognize as native/plugin identities;
+    // it may be an SSH private key instead.
+    if classified.native.is_empty() && classified.plugins.is_empty() && classified.unrecognized {
+        let data = fs::File::open(path).map_err(|source| IdentityError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let identity = age::ssh::Identity::from_buffer(
+            BufReader::new(data),
+            Some(path.to_string_lossy().into_owned()),
+        )
+        .map_err(|source| IdentityError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        return Ok(vec![Box::new(identity.with_callbacks(UiCallbacks))]);
+    }
+
+    into_identities(classified, path)
+}
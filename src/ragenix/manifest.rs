@@ -0,0 +1,60 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// A `secrets.lock` manifest: for every secret known at the time it was
+/// written, its declared recipient set and the BLAKE3 hash of its
+/// ciphertext. Written by [`super::rekey::rekey`] as a side effect of
+/// re-encrypting, and consumed (never written) by [`super::verify::verify`]
+/// to detect drift.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Manifest(BTreeMap<String, Entry>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub blake3: String,
+    pub recipients: Vec<String>,
+}
+
+impl Manifest {
+    pub fn entry(&self, key: &str) -> Option<&Entry> {
+        self.0.get(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.0.keys()
+    }
+
+    pub fn insert(&mut self, key: String, recipients: &[String], ciphertext: &[u8]) {
+        let mut recipients = recipients.to_vec();
+        recipients.sort();
+        self.0.insert(
+            key,
+            Entry {
+                recipients,
+                blake3: blake3::hash(ciphertext).to_hex().to_string(),
+            },
+        );
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.0.remove(key);
+    }
+
+    /// Loads the manifest at `path`, or an empty one if it doesn't exist yet.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|source| std::io::Error::new(std::io::ErrorKind::InvalidData, source))
+    }
+
+    /// Writes the manifest to `path` as canonical (sorted-key) JSON.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.0)
+            .expect("manifest serializes to JSON without error");
+        fs::write(path, format!("{json}\n"))
+    }
+}
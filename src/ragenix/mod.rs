@@ -0,0 +1,43 @@
+mod callback;
+mod cli;
+mod crypt;
+mod edit;
+mod error;
+mod identity;
+mod manifest;
+mod recipient;
+mod rekey;
+mod rules;
+mod verify;
+
+use clap::Parser;
+
+use cli::Cli;
+pub use error::Result;
+use rules::Rules;
+
+/// JSON Schema describing the attribute set a `secrets.nix` file must
+/// evaluate to. Exposed via `--schema` so editors/CI can validate rules
+/// files without invoking ragenix itself.
+const SCHEMA: &str = include_str!("agenix.schema.json");
+
+pub fn run() -> Result<()> {
+    let cli = Cli::parse();
+
+    if cli.schema {
+        print!("{SCHEMA}");
+        return Ok(());
+    }
+
+    let rules = Rules::load(&cli.rules)?;
+
+    if !cli.edit.is_empty() {
+        edit::edit(&cli.edit, &rules, &cli.identity)?;
+    } else if cli.rekey {
+        rekey::rekey(&rules, &cli.identity, cli.jobs, &cli.only)?;
+    } else if cli.verify {
+        verify::verify(&rules)?;
+    }
+
+    Ok(())
+}
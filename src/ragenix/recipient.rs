@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use age::plugin;
+use thiserror::Error;
+
+use super::callback::UiCallbacks;
+
+#[derive(Debug, Error)]
+pub enum RecipientError {
+    #[error(
+        "Invalid recipient: {recipient}\n\
+         Make sure you use an ssh-ed25519, ssh-rsa or an X25519 public key, \
+         or an age plugin recipient of the form age1<plugin>1..."
+    )]
+    Invalid { recipient: String },
+
+    #[error("Failed to resolve age plugin for recipient(s) of plugin '{plugin_name}': {source}")]
+    PluginResolve {
+        plugin_name: String,
+        #[source]
+        source: age::EncryptError,
+    },
+}
+
+/// A single parsed entry from a rule's `publicKeys` list.
+enum ParsedRecipient {
+    Ssh(age::ssh::Recipient),
+    X25519(age::x25519::Recipient),
+    /// An `age1<plugin>1...` recipient, handled by shelling out to
+    /// `age-plugin-<plugin>` via the age plugin protocol.
+    Plugin(plugin::Recipient),
+}
+
+fn parse_one(recipient: &str) -> Result<ParsedRecipient, RecipientError> {
+    if let Ok(r) = recipient.parse::<age::x25519::Recipient>() {
+        return Ok(ParsedRecipient::X25519(r));
+    }
+    if let Ok(r) = recipient.parse::<age::ssh::Recipient>() {
+        return Ok(ParsedRecipient::Ssh(r));
+    }
+    if let Ok(r) = recipient.parse::<plugin::Recipient>() {
+        return Ok(ParsedRecipient::Plugin(r));
+    }
+
+    Err(RecipientError::Invalid {
+        recipient: recipient.to_owned(),
+    })
+}
+
+/// Parses the `publicKeys` declared for a secret into the boxed [`age::Recipient`]s
+/// that should be used to encrypt it.
+///
+/// Plugin recipients (e.g. `age1yubikey1...`, `age1kms1...`) that share the same
+/// plugin name are batched into a single [`plugin::RecipientPluginV1`], since the
+/// plugin protocol wraps the file key for all of a plugin's recipients in one
+/// `age-plugin-<name>` session.
+pub fn parse(recipients: &[String]) -> Result<Vec<Box<dyn age::Recipient + Send>>, RecipientError> {
+    let mut plugin_recipients: HashMap<String, Vec<plugin::Recipient>> = HashMap::new();
+    let mut boxed: Vec<Box<dyn age::Recipient + Send>> = Vec::new();
+
+    for recipient in recipients {
+        match parse_one(recipient)? {
+            ParsedRecipient::X25519(r) => boxed.push(Box::new(r)),
+            ParsedRecipient::Ssh(r) => boxed.push(Box::new(r)),
+            ParsedRecipient::Plugin(r) => plugin_recipients
+                .entry(r.plugin().to_owned())
+                .or_default()
+                .push(r),
+        }
+    }
+
+    for (name, recipients) in plugin_recipients {
+        let plugin = plugin::RecipientPluginV1::new(&name, &recipients, &[], UiCallbacks).map_err(|source| {
+            RecipientError::PluginResolve {
+                plugin_name: name,
+                source,
+            }
+        })?;
+        boxed.push(Box::new(plugin));
+    }
+
+    Ok(boxed)
+}
@@ -0,0 +1,173 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use thiserror::Error;
+
+use super::{
+    crypt,
+    error::Result,
+    identity,
+    manifest::Manifest,
+    recipient,
+    rules::{normalize, Rules, Secret},
+};
+
+#[derive(Debug, Error)]
+pub enum RekeyError {
+    #[error("invalid --only pattern '{pattern}': {source}")]
+    InvalidGlob {
+        pattern: String,
+        #[source]
+        source: glob::PatternError,
+    },
+
+    #[error("--only pattern '{pattern}' did not match any secret")]
+    NoMatch { pattern: String },
+}
+
+/// The outcome of attempting to rekey a single secret, kept distinct from its
+/// stdout summary line so the parallel phase can finish before anything is
+/// printed.
+enum Outcome {
+    Rekeyed {
+        key: String,
+        path: PathBuf,
+        ciphertext: Vec<u8>,
+    },
+    Missing {
+        key: String,
+        path: PathBuf,
+    },
+}
+
+/// Re-encrypts every secret declared in `rules` (or, if `only` is non-empty,
+/// just the secrets whose key matches one of those globs) to its currently
+/// configured recipients, skipping secrets that don't exist on disk yet.
+///
+/// Secrets are decrypted/re-encrypted concurrently across a pool of `jobs`
+/// worker threads, with an `indicatif` progress bar (drawn to stderr)
+/// tracking completion. The stdout summary is still printed afterwards, in
+/// rules order, so it stays identical to the serial implementation.
+///
+/// Afterwards, the `secrets.lock` manifest is updated with an entry for
+/// every secret that was rekeyed (recipient set + BLAKE3 of the new
+/// ciphertext); entries for secrets outside of `only` are left untouched, so
+/// `--verify` has a complete and up-to-date baseline to compare against even
+/// after a selective rekey.
+pub fn rekey(rules: &Rules, identity_specs: &[String], jobs: usize, only: &[String]) -> Result<()> {
+    let selected = select(rules, only)?;
+    let identities = identity::load(identity_specs)?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .expect("thread pool parameters are valid");
+
+    let progress = ProgressBar::new(selected.len() as u64);
+    progress.set_style(
+        ProgressStyle::with_template("{bar:40.cyan/blue} {pos}/{len} {msg}")
+            .expect("progress bar template is valid"),
+    );
+
+    let outcomes: Vec<Result<Outcome>> = pool.install(|| {
+        selected
+            .par_iter()
+            .map(|key| {
+                let secret = &rules.secrets[*key];
+                progress.set_message((*key).clone());
+                let outcome = rekey_one(rules, key, secret, &identities);
+                progress.inc(1);
+                outcome
+            })
+            .collect()
+    });
+
+    progress.finish_and_clear();
+
+    let lock_path = rules.lock_path()?;
+    let mut manifest = Manifest::load(&lock_path)?;
+    for outcome in outcomes {
+        match outcome? {
+            Outcome::Rekeyed { key, path, ciphertext } => {
+                println!("Rekeying {}", path.display());
+                let recipients = &rules.secrets[&key].public_keys;
+                manifest.insert(key, recipients, &ciphertext);
+            }
+            Outcome::Missing { key, path } => {
+                println!("Does not exist, ignored: {}", path.display());
+                manifest.remove(&key);
+            }
+        }
+    }
+    manifest.save(&lock_path)?;
+
+    Ok(())
+}
+
+fn rekey_one(
+    rules: &Rules,
+    key: &str,
+    secret: &Secret,
+    identities: &[Box<dyn age::Identity + Send + Sync>],
+) -> Result<Outcome> {
+    let path = rules.secret_path(key)?;
+
+    if !path.exists() {
+        return Ok(Outcome::Missing {
+            key: key.to_owned(),
+            path,
+        });
+    }
+
+    let plaintext = crypt::decrypt(&path, identities)?;
+    let recipients = recipient::parse(&secret.public_keys)?;
+    let ciphertext = crypt::encrypt(recipients, &plaintext)?;
+
+    std::fs::write(&path, &ciphertext)?;
+
+    Ok(Outcome::Rekeyed {
+        key: key.to_owned(),
+        path,
+        ciphertext,
+    })
+}
+
+/// Resolves `only` (a list of `--only` globs) to the set of rule keys they
+/// match, or every declared key if `only` is empty. Each pattern must match
+/// at least one key.
+///
+/// Both the pattern and each rule key are absolutized against the rules
+/// directory and dedotted (via [`normalize`]) before comparing, so
+/// `./foo/*.age`, `foo/*.age`, and an absolute `--only <rules-dir>/foo/*.age`
+/// all match the same declared secrets.
+fn select<'a>(rules: &'a Rules, only: &[String]) -> Result<Vec<&'a String>> {
+    if only.is_empty() {
+        return Ok(rules.secrets.keys().collect());
+    }
+
+    let base = rules.dir()?;
+    let mut selected = BTreeSet::new();
+    for pattern in only {
+        let normalized_pattern = normalize(&base, pattern).to_string_lossy().into_owned();
+        let glob = glob::Pattern::new(&normalized_pattern).map_err(|source| RekeyError::InvalidGlob {
+            pattern: pattern.clone(),
+            source,
+        })?;
+
+        let mut matched = false;
+        for key in rules.secrets.keys() {
+            if glob.matches(&normalize(&base, key).to_string_lossy()) {
+                selected.insert(key);
+                matched = true;
+            }
+        }
+        if !matched {
+            return Err(RekeyError::NoMatch {
+                pattern: pattern.clone(),
+            }
+            .into());
+        }
+    }
+    Ok(selected.into_iter().collect())
+}
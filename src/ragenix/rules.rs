@@ -0,0 +1,171 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+use jsonschema::JSONSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// JSON Schema the evaluated `secrets.nix` attribute set must conform to.
+const SCHEMA: &str = include_str!("agenix.schema.json");
+
+/// A single secret declaration: the set of recipients it is (or should be)
+/// encrypted to.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Secret {
+    #[serde(rename = "publicKeys")]
+    pub public_keys: Vec<String>,
+}
+
+/// The parsed and validated contents of a `secrets.nix` file.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    pub path: PathBuf,
+    pub secrets: BTreeMap<String, Secret>,
+}
+
+#[derive(Debug, Error)]
+pub enum RulesError {
+    #[error("failed to evaluate secrets rules '{}': {message}", path.display())]
+    Eval { path: PathBuf, message: String },
+
+    #[error("failed to parse the output of evaluating '{}' as JSON", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("secrets rules are invalid: '{}'\n{}", path.display(), errors.join("\n"))]
+    Invalid { path: PathBuf, errors: Vec<String> },
+}
+
+impl Rules {
+    /// Evaluates `path` as a Nix expression, validates the result against
+    /// [`SCHEMA`], and returns the declared secrets.
+    pub fn load(path: &Path) -> Result<Self, RulesError> {
+        let value = eval(path)?;
+
+        let schema: Value = serde_json::from_str(SCHEMA).expect("schema is valid JSON");
+        let compiled = JSONSchema::compile(&schema).expect("schema is a valid JSON Schema");
+
+        if let Err(errors) = compiled.validate(&value) {
+            let errors = errors
+                .map(|error| format!(" - {}: {}", error.instance_path, error))
+                .collect();
+            return Err(RulesError::Invalid {
+                path: path.to_path_buf(),
+                errors,
+            });
+        }
+
+        let secrets: BTreeMap<String, Secret> =
+            serde_json::from_value(value).map_err(|source| RulesError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
+
+        Ok(Rules {
+            path: path.to_path_buf(),
+            secrets,
+        })
+    }
+
+    /// Resolves the directory secrets are read/written relative to: the
+    /// directory containing the rules file (matching agenix convention:
+    /// secrets live next to `secrets.nix`).
+    pub fn dir(&self) -> std::io::Result<PathBuf> {
+        let parent = self.path.parent().unwrap_or_else(|| Path::new(""));
+        if parent.as_os_str().is_empty() || parent == Path::new(".") {
+            std::env::current_dir()
+        } else if parent.is_absolute() {
+            Ok(parent.to_path_buf())
+        } else {
+            Ok(std::env::current_dir()?.join(parent))
+        }
+    }
+
+    /// Resolves the on-disk path of the secret declared under `key`, relative
+    /// to [`Rules::dir`].
+    pub fn secret_path(&self, key: &str) -> std::io::Result<PathBuf> {
+        Ok(self.dir()?.join(key))
+    }
+
+    /// Resolves the path of the `secrets.lock` integrity manifest, next to
+    /// the rules file.
+    pub fn lock_path(&self) -> std::io::Result<PathBuf> {
+        self.secret_path("secrets.lock")
+    }
+
+    /// Finds the declared key whose path, once normalized against
+    /// [`Rules::dir`] (see [`normalize`]), matches `target`'s. Used to make
+    /// `--edit` accept `./foo.age`, `foo.age`, and an absolute path to the
+    /// same declared secret interchangeably.
+    pub fn find_key(&self, target: &str) -> std::io::Result<Option<&String>> {
+        let base = self.dir()?;
+        let target = normalize(&base, target);
+        Ok(self
+            .secrets
+            .keys()
+            .find(|key| normalize(&base, key) == target))
+    }
+}
+
+/// Normalizes `path` (a rule key or a user-supplied `--edit`/`--only` value)
+/// by absolutizing it against `base` if it isn't already absolute, then
+/// lexically resolving `.` and `..` components (without touching the
+/// filesystem, so this works equally well on glob patterns containing
+/// wildcards). This makes `./foo/*.age`, `foo/*.age`, and
+/// `<base>/foo/*.age` compare equal.
+pub fn normalize(base: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Evaluates `path` with `nix-instantiate`, producing the JSON representation
+/// of the attribute set it returns. `secrets.nix` is a Nix expression (it may
+/// use `let`, `import`, functions, etc.), so we defer evaluation to Nix itself
+/// rather than re-implementing a Nix evaluator.
+fn eval(path: &Path) -> Result<Value, RulesError> {
+    let output = Command::new("nix-instantiate")
+        .arg("--eval")
+        .arg("--strict")
+        .arg("--json")
+        .arg(path)
+        .output()
+        .map_err(|source| RulesError::Eval {
+            path: path.to_path_buf(),
+            message: source.to_string(),
+        })?;
+
+    if !output.status.success() {
+        return Err(RulesError::Eval {
+            path: path.to_path_buf(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+
+    serde_json::from_slice(&output.stdout).map_err(|source| RulesError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader, Cursor},
+};
+
+use age::armor::ArmoredReader;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::{error::Result, manifest::Manifest, rules::Rules};
+
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error("secrets verification failed:\n{}", .0.join("\n"))]
+    Inconsistent(Vec<String>),
+
+    #[error(
+        "no secrets.lock found at '{}'; run --rekey at least once before --verify can check for drift",
+        path.display()
+    )]
+    NoLock { path: std::path::PathBuf },
+
+    #[error("{} does not look like an age-encrypted file", path.display())]
+    NotAnAgeFile { path: std::path::PathBuf },
+}
+
+/// Walks every secret declared in `rules`, confirming that the `*.age` file
+/// on disk is encrypted to exactly its declared recipients and that its
+/// ciphertext matches the `secrets.lock` manifest, without modifying
+/// anything. Also flags secrets present on disk but undeclared in `rules`
+/// (and vice versa).
+///
+/// `secrets.lock` is written by [`super::rekey::rekey`], so a rules file
+/// that has never been rekeyed has no manifest to compare against; that
+/// case is reported as [`VerifyError::NoLock`] rather than as one spurious
+/// "not recorded in secrets.lock" issue per declared secret.
+///
+/// Returns [`VerifyError::Inconsistent`], carrying the full diff summary, if
+/// anything doesn't line up.
+pub fn verify(rules: &Rules) -> Result<()> {
+    let lock_path = rules.lock_path()?;
+    if !lock_path.exists() {
+        return Err(VerifyError::NoLock { path: lock_path }.into());
+    }
+    let manifest = Manifest::load(&lock_path)?;
+
+    let mut issues = Vec::new();
+
+    for (key, secret) in &rules.secrets {
+        let path = rules.secret_path(key)?;
+
+        if !path.exists() {
+            issues.push(format!(" - {key}: declared in rules but missing on disk"));
+            continue;
+        }
+
+        let ciphertext = std::fs::read(&path)?;
+        let stanzas = match inspect(&ciphertext) {
+            Ok(stanzas) => stanzas,
+            Err(_) => {
+                issues.push(format!(" - {key}: {}", VerifyError::NotAnAgeFile { path }));
+                continue;
+            }
+        };
+
+        // `age::Encryptor` always appends one "grease" decoy stanza to every
+        // file it produces (to stop ciphertexts from leaking their true
+        // recipient count), so the on-disk stanza count can legitimately be
+        // one higher than the declared recipient count, but never lower and
+        // never higher than that.
+        let declared = secret.public_keys.len();
+        if stanzas.count < declared || stanzas.count > declared + 1 {
+            issues.push(format!(
+                " - {key}: encrypted to {} recipient(s) (+ grease), but {} are declared",
+                stanzas.count, declared
+            ));
+        }
+        for recipient in &secret.public_keys {
+            if let Some(tag) = ssh_recipient_tag(recipient) {
+                if !stanzas.ssh_tags.contains(&tag) {
+                    issues.push(format!(
+                        " - {key}: not encrypted to declared recipient {recipient}"
+                    ));
+                }
+            }
+        }
+
+        let hash = blake3::hash(&ciphertext).to_hex().to_string();
+        match manifest.entry(key) {
+            Some(entry) => {
+                if entry.blake3 != hash {
+                    issues.push(format!(" - {key}: ciphertext does not match secrets.lock (tampered or edited out-of-band)"));
+                }
+
+                // `inspect`'s stanza-count/ssh-tag check above can't see
+                // X25519 or plugin recipients at all (age hides who a file
+                // is encrypted to), so it would miss e.g. a dropped X25519
+                // recipient entirely. Comparing against the recipient set
+                // recorded at the last --rekey catches that too, since any
+                // declared-recipient change leaves the ciphertext (and thus
+                // this recorded set) stale until the next --rekey.
+                let mut declared = secret.public_keys.clone();
+                declared.sort();
+                if entry.recipients != declared {
+                    issues.push(format!(
+                        " - {key}: declared recipients changed since secrets.lock was last recorded (run --rekey)"
+                    ));
+                }
+            }
+            None => issues.push(format!(" - {key}: not recorded in secrets.lock")),
+        }
+    }
+
+    for key in manifest.keys() {
+        if !rules.secrets.contains_key(key) {
+            issues.push(format!(
+                " - {key}: recorded in secrets.lock but no longer declared in rules"
+            ));
+        }
+    }
+
+    for entry in std::fs::read_dir(rules.dir()?)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "age") {
+            let key = path
+                .file_name()
+                .expect("path from read_dir has a file name")
+                .to_string_lossy()
+                .into_owned();
+            if !rules.secrets.contains_key(&key) {
+                issues.push(format!(
+                    " - {key}: *.age file on disk but not declared in rules"
+                ));
+            }
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        issues.sort();
+        issues.dedup();
+        Err(VerifyError::Inconsistent(issues).into())
+    }
+}
+
+struct Stanzas {
+    count: usize,
+    ssh_tags: HashSet<String>,
+}
+
+/// Parses just enough of an age file's header (de-armoring it first, if
+/// necessary) to count its recipient stanzas and collect the ssh-* ones'
+/// recipient tags, without needing any identity to actually decrypt it.
+///
+/// X25519 and plugin stanzas carry no recoverable recipient tag (by design,
+/// age does not reveal who a file is encrypted to), so for those we can only
+/// confirm the stanza *count* matches the number of declared recipients.
+fn inspect(ciphertext: &[u8]) -> std::io::Result<Stanzas> {
+    let armored = ArmoredReader::new(Cursor::new(ciphertext));
+    let mut reader = BufReader::new(armored);
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != "age-encryption.org/v1" {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "missing age version line",
+        ));
+    }
+
+    let mut count = 0;
+    let mut ssh_tags = HashSet::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "truncated age header",
+            ));
+        }
+        let line = line.trim_end_matches('\n');
+        if let Some(stanza) = line.strip_prefix("-> ") {
+            count += 1;
+            let mut fields = stanza.split(' ');
+            if matches!(fields.next(), Some("ssh-rsa") | Some("ssh-ed25519")) {
+                if let Some(tag) = fields.next() {
+                    ssh_tags.insert(tag.to_owned());
+                }
+            }
+        } else if line.starts_with("---") {
+            break;
+        }
+    }
+
+    Ok(Stanzas { count, ssh_tags })
+}
+
+/// Computes the age recipient tag (base64, no padding, of the first 4 bytes
+/// of SHA-256 over the SSH wire-format key) for an `ssh-rsa`/`ssh-ed25519`
+/// recipient string, or `None` for any other recipient kind.
+fn ssh_recipient_tag(recipient: &str) -> Option<String> {
+    let mut fields = recipient.split_whitespace();
+    match fields.next()? {
+        "ssh-rsa" | "ssh-ed25519" => {}
+        _ => return None,
+    }
+    let blob = STANDARD.decode(fields.next()?).ok()?;
+    let digest = Sha256::digest(blob);
+    Some(base64::engine::general_purpose::STANDARD_NO_PAD.encode(&digest[..4]))
+}
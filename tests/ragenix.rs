@@ -1,3 +1,4 @@
+use age::secrecy::ExposeSecret;
 use assert_cmd::{crate_name, Command};
 use color_eyre::Result;
 use copy_dir::copy_dir;
@@ -88,7 +89,7 @@ fn edit_new_entry() -> Result<()> {
 
     let mut cmd = Command::cargo_bin(crate_name!())?;
     let assert = cmd
-        .current_dir(&dir.path())
+        .current_dir(dir.path())
         .arg("--edit")
         .arg("pandora.age")
         .env("EDITOR", format!("cp {}", &pandora.display()))
@@ -301,6 +302,303 @@ fn rejects_invalid_rules() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn edit_accepts_multiple_files() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd
+        .current_dir(&path)
+        .arg("--edit")
+        .arg("github-runner.token.age")
+        .arg("root.passwd.age")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .env("EDITOR", "true")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains("github-runner.token.age wasn't changed"))
+        .stdout(predicate::str::contains("root.passwd.age wasn't changed"));
+
+    Ok(())
+}
+
+#[test]
+fn edit_fails_for_undeclared_path() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd
+        .current_dir(&path)
+        .arg("--edit")
+        .arg("not-declared.age")
+        .env("EDITOR", "true")
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "'not-declared.age' is not declared in",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn edit_dedots_path_against_declared_key() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd
+        .current_dir(&path)
+        .arg("--edit")
+        .arg("./root.passwd.age")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .env("EDITOR", "true")
+        .assert();
+
+    assert.success();
+
+    Ok(())
+}
+
+#[test]
+fn only_selects_matching_secret() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd
+        .current_dir(&path)
+        .arg("--rekey")
+        .arg("--only")
+        .arg("root.passwd.age")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains("Rekeying"))
+        .stdout(predicate::str::contains("root.passwd.age"))
+        .stdout(predicate::str::contains("github-runner.token.age").not());
+
+    Ok(())
+}
+
+#[test]
+fn only_dedot_and_absolute_patterns_match_relative_keys() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+    let absolute = path.join("root.passwd.age");
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd
+        .current_dir(&path)
+        .arg("--rekey")
+        .arg("--only")
+        .arg("./root.passwd.age")
+        .arg("--only")
+        .arg(&absolute)
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .assert();
+
+    assert
+        .success()
+        .stdout(predicate::str::contains("root.passwd.age"))
+        .stdout(predicate::str::contains("github-runner.token.age").not());
+
+    Ok(())
+}
+
+#[test]
+fn only_fails_if_pattern_matches_nothing() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd
+        .current_dir(&path)
+        .arg("--rekey")
+        .arg("--only")
+        .arg("does-not-exist.age")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "--only pattern 'does-not-exist.age' did not match any secret",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn verify_fails_without_lock() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd.current_dir(&path).arg("--verify").assert();
+
+    assert
+        .failure()
+        .stderr(predicate::str::contains("no secrets.lock found"))
+        .stderr(predicate::str::contains("--rekey"));
+
+    Ok(())
+}
+
+#[test]
+fn verify_passes_after_rekey() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    Command::cargo_bin(crate_name!())?
+        .current_dir(&path)
+        .arg("--rekey")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd.current_dir(&path).arg("--verify").assert();
+
+    assert.success().stdout("");
+
+    Ok(())
+}
+
+#[test]
+fn verify_detects_stale_x25519_recipients() -> Result<()> {
+    // Swapping one X25519 recipient for another leaves the stanza count
+    // unchanged and the ssh-tag check inapplicable (X25519 recipients carry
+    // no recoverable tag), so this only gets caught by comparing against the
+    // recipient set secrets.lock recorded at the last --rekey.
+    let identity = age::x25519::Identity::generate();
+    let kept = age::x25519::Identity::generate().to_public();
+    let dropped = age::x25519::Identity::generate().to_public();
+
+    let dir = tempfile::tempdir()?;
+    let identity_path = dir.path().join("identity.txt");
+    fs::write(&identity_path, identity.to_string().expose_secret())?;
+
+    let write_rules = |recipients: &str| -> Result<()> {
+        fs::File::create(dir.path().join("secrets.nix")).and_then(|mut f| {
+            f.write_all(formatdoc! {r#"
+                {{ "secret.age".publicKeys = [ {} ]; }}
+            "#, recipients}.as_bytes())
+        })?;
+        Ok(())
+    };
+
+    write_rules(&format!(
+        "\"{}\" \"{}\"",
+        identity.to_public(),
+        kept,
+    ))?;
+
+    Command::cargo_bin(crate_name!())?
+        .current_dir(dir.path())
+        .arg("--edit")
+        .arg("secret.age")
+        .env("EDITOR", "true")
+        .assert()
+        .success();
+
+    Command::cargo_bin(crate_name!())?
+        .current_dir(dir.path())
+        .arg("--rekey")
+        .arg("--identity")
+        .arg(&identity_path)
+        .assert()
+        .success();
+
+    write_rules(&format!("\"{}\" \"{}\"", identity.to_public(), dropped))?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd.current_dir(dir.path()).arg("--verify").assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "secret.age: declared recipients changed since secrets.lock was last recorded",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn verify_detects_blake3_drift() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    Command::cargo_bin(crate_name!())?
+        .current_dir(&path)
+        .arg("--rekey")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .assert()
+        .success();
+
+    let secret = path.join("root.passwd.age");
+    let mut ciphertext = fs::read(&secret)?;
+    ciphertext.push(b'\n');
+    fs::write(&secret, ciphertext)?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd.current_dir(&path).arg("--verify").assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "root.passwd.age: ciphertext does not match secrets.lock",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn verify_detects_declared_but_missing() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    Command::cargo_bin(crate_name!())?
+        .current_dir(&path)
+        .arg("--rekey")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .assert()
+        .success();
+
+    fs::remove_file(path.join("root.passwd.age"))?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd.current_dir(&path).arg("--verify").assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "root.passwd.age: declared in rules but missing on disk",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn verify_detects_undeclared_on_disk() -> Result<()> {
+    let (_dir, path) = copy_example_to_tmpdir()?;
+
+    Command::cargo_bin(crate_name!())?
+        .current_dir(&path)
+        .arg("--rekey")
+        .arg("--identity")
+        .arg("keys/id_ed25519")
+        .assert()
+        .success();
+
+    fs::copy(path.join("root.passwd.age"), path.join("extra-secret.age"))?;
+
+    let mut cmd = Command::cargo_bin(crate_name!())?;
+    let assert = cmd.current_dir(&path).arg("--verify").assert();
+
+    assert.failure().stderr(predicate::str::contains(
+        "extra-secret.age: *.age file on disk but not declared in rules",
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn fails_for_invalid_recipient() -> Result<()> {
     let dir = tempfile::tempdir()?;
@@ -315,7 +613,7 @@ fn fails_for_invalid_recipient() -> Result<()> {
 
     let mut cmd = Command::cargo_bin(crate_name!())?;
     let assert = cmd
-        .current_dir(&dir.path())
+        .current_dir(dir.path())
         .arg("--edit")
         .arg("wurzelpfropf.txt.age")
         .env("EDITOR", "true")